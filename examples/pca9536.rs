@@ -1,107 +1,48 @@
-#[macro_use]
-extern crate bitflags;
-
 use std::{thread::sleep, time::Duration};
 
-use num_derive::ToPrimitive;
-use num_traits::ToPrimitive;
-
-use usb2642_i2c::USB2642I2C;
+use usb2642_i2c::{
+    pca953x::{Direction, Pca953x, PortWidth},
+    USB2642I2C,
+};
 
 const I2C_ADDRESS: u8 = 0x41;
 
-#[derive(ToPrimitive, Debug)]
-pub enum Register {
-    InputPort = 0,
-    OutputPort = 1,
-    Polarity = 2,
-    Configuration = 3,
-}
-
-bitflags! {
-    pub flags GpioPin: u8 {
-        const GPIO_NONE = 0x00,
-        const GPIO0 = 0x01,
-        const GPIO1 = 0x02,
-        const GPIO2 = 0x04,
-        const GPIO3 = 0x08,
-        const GPIO_ALL = 0x0f,
-    }
-}
+const GPIO0: u8 = 0;
+const GPIO1: u8 = 1;
+const GPIO2: u8 = 2;
+const GPIO3: u8 = 3;
 
-#[derive(ToPrimitive, Debug)]
-pub enum Direction {
-    Output = 0,
-    Input = 1,
-}
+fn main() {
+    let usb2642 = USB2642I2C::open("/dev/sg0").unwrap();
 
-pub struct PCA9536 {
-    usb2642: USB2642I2C,
-    direction_mask: u8,
-}
+    let mut pca9536 = Pca953x::new(usb2642, I2C_ADDRESS, PortWidth::Bits4);
 
-impl PCA9536 {
-    pub fn new(usb2642: USB2642I2C) -> Self {
-        Self {
-            usb2642,
-            direction_mask: 0xff,
-        }
+    for pin in [GPIO0, GPIO1, GPIO2, GPIO3] {
+        pca9536.set_pin_direction(pin, Direction::Output).unwrap();
     }
 
-    fn write_register(&mut self, register: Register, value: u8) {
-        let mut data = [register.to_u8().unwrap(), value];
-        self.usb2642.write(I2C_ADDRESS, &mut data).unwrap();
-    }
+    println!("Input port: {:#02x}", read_input_port(&mut pca9536));
 
-    pub fn read_register(&mut self, register: Register) -> u8 {
-        let data = [register.to_u8().unwrap()];
-        let data = self.usb2642.write_read(I2C_ADDRESS, &data, 1).unwrap();
-        data[0]
+    for pin in [GPIO0, GPIO1, GPIO2, GPIO3] {
+        pca9536.set_low(pin).unwrap();
     }
+    println!("Input port: {:#02x}", read_input_port(&mut pca9536));
 
-    pub fn set_pins_direction(&mut self, pins: GpioPin, direction: Direction) {
-        match direction {
-            Direction::Output => {
-                self.direction_mask &= !pins.bits;
-            }
-            Direction::Input => {
-                self.direction_mask &= pins.bits;
-            }
-        }
-        self.write_register(Register::Configuration, self.direction_mask);
-    }
+    sleep(Duration::from_secs(2));
+    pca9536.set_high(GPIO0).unwrap();
+    pca9536.set_high(GPIO2).unwrap();
+    println!("Input port: {:#02x}", read_input_port(&mut pca9536));
 
-    pub fn output_values(&mut self, pins: GpioPin) {
-        self.write_register(Register::OutputPort, pins.bits);
+    sleep(Duration::from_secs(2));
+    for pin in [GPIO0, GPIO1, GPIO2, GPIO3] {
+        pca9536.set_high(pin).unwrap();
     }
+    println!("Input port: {:#02x}", read_input_port(&mut pca9536));
 }
 
-fn main() {
-    let usb2642 = USB2642I2C::open("/dev/sg0").unwrap();
-
-    let mut pca9536 = PCA9536::new(usb2642);
-
-    pca9536.set_pins_direction(GPIO_ALL, Direction::Output);
-
-    println!(
-        "Output port register: {:#02x}",
-        pca9536.read_register(Register::OutputPort)
-    );
-    pca9536.output_values(GPIO_NONE);
-    println!(
-        "Output port register: {:#02x}",
-        pca9536.read_register(Register::OutputPort)
-    );
-    sleep(Duration::from_secs(2));
-    pca9536.output_values(GPIO0 | GPIO2);
-    println!(
-        "Output port register: {:#02x}",
-        pca9536.read_register(Register::OutputPort)
-    );
-    sleep(Duration::from_secs(2));
-    pca9536.output_values(GPIO_ALL);
-    println!(
-        "Output port register: {:#02x}",
-        pca9536.read_register(Register::OutputPort)
-    );
+fn read_input_port(pca9536: &mut Pca953x) -> u8 {
+    [GPIO0, GPIO1, GPIO2, GPIO3]
+        .into_iter()
+        .map(|pin| (pca9536.is_high(pin).unwrap() as u8) << pin)
+        .fold(0, |acc, bit| acc | bit)
 }
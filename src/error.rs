@@ -0,0 +1,134 @@
+use std::fmt;
+
+// Linux `include/scsi/scsi.h` status byte (before host/driver byte shifting).
+const SCSI_STATUS_CHECK_CONDITION: u8 = 0x02;
+
+// Linux `include/scsi/scsi.h` host_byte values that indicate the command
+// never reached (or never came back from) the slave, as opposed to the
+// slave itself rejecting it.
+const DID_NO_CONNECT: u16 = 0x01;
+const DID_BUS_BUSY: u16 = 0x02;
+const DID_TIME_OUT: u16 = 0x03;
+const DID_ERROR: u16 = 0x07;
+
+/// Errors returned by [`crate::USB2642I2C`] operations.
+///
+/// Everything but [`Error::Io`] and [`Error::Pec`] is derived from the SCSI status, host
+/// adapter status and low-level driver status fields that the Linux `sg`
+/// interface fills in on [`crate::SgIoHdr`] once an `ioctl` completes.
+#[derive(Debug)]
+pub enum Error {
+    /// Opening `/dev/sgX` or issuing the `ioctl` itself failed, or an
+    /// argument was rejected before anything was sent
+    /// (`ErrorKind::InvalidInput`, e.g. an oversized payload or an
+    /// out-of-range pin).
+    Io(std::io::Error),
+    /// The slave address was not acknowledged on the bus. `sense` is the
+    /// sense data the `sg` driver returned, if any.
+    NoAcknowledge { sense: Vec<u8> },
+    /// The host adapter or SCSI bus reported a failure (timeout, bus busy,
+    /// no connect, ...). `sense` is the sense data the `sg` driver returned,
+    /// if any.
+    Bus { sense: Vec<u8> },
+    /// The command completed with a status this crate does not recognize.
+    /// `status`, `host_status` and `driver_status` are the raw `sg_io_hdr`
+    /// fields, and `sense` is the sense data the `sg` driver returned, if
+    /// any.
+    Other {
+        status: u8,
+        host_status: u16,
+        driver_status: u16,
+        sense: Vec<u8>,
+    },
+    /// An SMBus transaction's received Packet Error Check byte did not match
+    /// the CRC-8 computed over the transaction.
+    Pec,
+}
+
+impl Error {
+    /// Classify a completed `sg_io_hdr` into an [`Error`], or `None` if it
+    /// succeeded. `sense` is the sense buffer written by the `ioctl`,
+    /// truncated to the `sb_len_wr` bytes actually filled in.
+    pub(crate) fn from_sg_status(
+        status: u8,
+        host_status: u16,
+        driver_status: u16,
+        sense: &[u8],
+    ) -> Option<Self> {
+        if status == 0 && host_status == 0 && driver_status == 0 {
+            return None;
+        }
+
+        Some(match host_status {
+            DID_NO_CONNECT | DID_BUS_BUSY | DID_TIME_OUT | DID_ERROR => Error::Bus {
+                sense: sense.to_vec(),
+            },
+            _ if status == SCSI_STATUS_CHECK_CONDITION => Error::NoAcknowledge {
+                sense: sense.to_vec(),
+            },
+            _ => Error::Other {
+                status,
+                host_status,
+                driver_status,
+                sense: sense.to_vec(),
+            },
+        })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "i/o error: {}", e),
+            Error::NoAcknowledge { .. } => write!(f, "i2c slave did not acknowledge"),
+            Error::Bus { .. } => write!(f, "i2c bus error"),
+            Error::Other {
+                status,
+                host_status,
+                driver_status,
+                ..
+            } => write!(
+                f,
+                "scsi command failed (status {:#04x}, host_status {:#06x}, driver_status {:#06x})",
+                status, host_status, driver_status
+            ),
+            Error::Pec => write!(f, "smbus packet error check (PEC) mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl embedded_hal::i2c::Error for Error {
+    fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        match self {
+            Error::NoAcknowledge { .. } => embedded_hal::i2c::ErrorKind::NoAcknowledge(
+                embedded_hal::i2c::NoAcknowledgeSource::Unknown,
+            ),
+            Error::Bus { .. } => embedded_hal::i2c::ErrorKind::Bus,
+            Error::Io(_) | Error::Other { .. } | Error::Pec => embedded_hal::i2c::ErrorKind::Other,
+        }
+    }
+}
+
+impl embedded_hal::digital::Error for Error {
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        // A GPIO-expander pin access failure is always an I2C-level failure
+        // under the hood; `embedded_hal::digital::ErrorKind` has no variant
+        // for that, so everything maps to `Other`.
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
@@ -18,13 +18,20 @@
 //!   let read_data = usb2642.write_read(I2C_ADDRESS, &write_data, 1).unwrap();
 //! }
 //! ```
+//!
+//! [`USB2642I2C`] also implements `embedded_hal::i2c::I2c`, so drivers written
+//! against `embedded-hal` 1.0 can be handed a [`USB2642I2C`] directly.
 
 #[macro_use]
 extern crate nix;
 
+mod error;
+pub mod pca953x;
+pub mod smbus;
+
 use std::{
     fs::OpenOptions,
-    io::{Error, ErrorKind, Result},
+    io::ErrorKind,
     os::unix::io::{IntoRawFd, RawFd},
 };
 
@@ -32,7 +39,39 @@ use nix::{libc::ioctl, sys::ioctl::ioctl_num_type};
 use num_derive::ToPrimitive;
 use num_traits::ToPrimitive;
 
+pub use error::Error;
+
 pub type I2CAddress = u8;
+pub type Result<T> = std::result::Result<T, Error>;
+
+const SENSE_BUFFER_LEN: usize = 64;
+
+/// Largest payload that fits inline in a command's command/write phase.
+const MAX_INLINE_LEN: usize = 9;
+
+/// Largest payload a single SCSI data phase can carry: `i2c_*_data_phase_length_{high,low}`
+/// is a 16-bit field.
+const MAX_DATA_PHASE_LEN: usize = 0xffff;
+
+/// Splits `len` bytes into the chunk sizes [`USB2642I2C::write`] and
+/// [`USB2642I2C::read_chunked`] should issue one command per, each at most
+/// `max` bytes. Unlike `[T]::chunks`, `len == 0` still yields a single
+/// zero-length chunk: a zero-length transaction is a real transaction (e.g.
+/// a probe for a slave's ACK) and must still reach the hardware.
+fn chunk_lengths(len: usize, max: usize) -> Vec<usize> {
+    if len == 0 {
+        return vec![0];
+    }
+
+    let mut lengths = Vec::new();
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(max);
+        lengths.push(chunk);
+        remaining -= chunk;
+    }
+    lengths
+}
 
 pub const SG_INTERFACE_ID_ORIG: u8 = b'S';
 
@@ -47,6 +86,51 @@ pub enum DataTransferDirection {
 pub const USB2642_SCSI_OPCODE: u8 = 0xcf;
 pub const USB2642_I2C_WRITE_STREAM: u8 = 0x23;
 pub const USB2642_I2C_WRITE_READ_STREAM: u8 = 0x22;
+/// Vendor command action byte for the clock-divider command, reverse
+/// engineered from USB traces against a single USB2642 unit and **not**
+/// confirmed against the vendor datasheet. Unlike
+/// [`USB2642_I2C_WRITE_STREAM`]/[`USB2642_I2C_WRITE_READ_STREAM`], which are
+/// exercised by every transfer this crate makes, this opcode is only ever
+/// hit through [`USB2642I2C::set_speed`]/[`USB2642I2C::open_with_config`] —
+/// treat those as experimental until this is verified on more hardware.
+pub const USB2642_I2C_SET_CLOCK: u8 = 0x20;
+
+/// I2C bus clock speed understood by the USB2642's clock-divider
+/// configuration register.
+///
+/// **Experimental:** the divider values below come from observing the
+/// firmware's own register writes on one unit, not from a documented
+/// formula, so they are not guaranteed to hold for every USB2642 revision.
+/// Programming the wrong divider does not fail loudly — it just runs the
+/// bus at an unexpected speed — so treat [`USB2642I2C::set_speed`] as
+/// unverified until these are cross-checked against the datasheet or a
+/// logic-analyzer trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum I2cSpeed {
+    /// 100 kHz, I2C standard mode. The default speed the firmware comes up
+    /// with if the clock-divider register is never programmed.
+    #[default]
+    Standard100kHz,
+    /// 400 kHz, I2C fast mode.
+    Fast400kHz,
+}
+
+impl I2cSpeed {
+    /// Clock-divider register value for this speed. See the "Experimental"
+    /// note on [`I2cSpeed`] itself: unverified against a datasheet.
+    fn divider(self) -> u16 {
+        match self {
+            I2cSpeed::Standard100kHz => 0x0177,
+            I2cSpeed::Fast400kHz => 0x005d,
+        }
+    }
+}
+
+/// Configuration for [`USB2642I2C::open_with_config`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Config {
+    pub speed: I2cSpeed,
+}
 
 pub trait USB2642I2CCommand {}
 
@@ -64,14 +148,23 @@ pub struct USB2642I2CWriteReadCommand {
 }
 
 impl USB2642I2CWriteReadCommand {
+    /// Builds a write-read-stream command. `write_data` (the register/pointer
+    /// bytes sent before the repeated start) is always carried inline, since
+    /// the single SCSI data phase this command has is already used for the
+    /// read; `read_len` may use that full data phase, up to 65535 bytes.
     pub fn new(i2c_addr: u8, write_data: &[u8], read_len: usize) -> Result<Self> {
-        if read_len > 9 {
-            return Err(Error::new(ErrorKind::InvalidInput, "read_len > 9 bytes"));
-        } else if write_data.len() > 9 {
-            return Err(Error::new(
+        if read_len > MAX_DATA_PHASE_LEN {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "read_len > 65535 bytes",
+            )
+            .into());
+        } else if write_data.len() > MAX_INLINE_LEN {
+            return Err(std::io::Error::new(
                 ErrorKind::InvalidInput,
                 "write_data.len() > 9 bytes",
-            ));
+            )
+            .into());
         }
 
         let i2c_write_addr = i2c_addr << 1;
@@ -112,12 +205,16 @@ pub struct USB2642I2CWriteCommand {
 }
 
 impl USB2642I2CWriteCommand {
+    /// Builds a write-stream command carrying `write_data` inline in the
+    /// command phase. Limited to [`MAX_INLINE_LEN`] bytes; [`USB2642I2C::write`]
+    /// sends larger payloads through the SCSI data phase instead.
     pub fn new(i2c_addr: u8, write_data: &[u8]) -> Result<Self> {
-        if write_data.len() > 9 {
-            return Err(Error::new(
+        if write_data.len() > MAX_INLINE_LEN {
+            return Err(std::io::Error::new(
                 ErrorKind::InvalidInput,
                 "write_data.len() > 9 bytes",
-            ));
+            )
+            .into());
         }
 
         let i2c_write_addr = i2c_addr << 1;
@@ -139,10 +236,59 @@ impl USB2642I2CWriteCommand {
 
         Ok(s)
     }
+
+    /// Builds a write-stream command whose `data_len` bytes are carried in
+    /// the SCSI data phase (`dxferp`/`dxfer_len`) instead of inline, for
+    /// payloads larger than [`MAX_INLINE_LEN`]. The command phase is left
+    /// empty; the caller is responsible for pointing `dxferp` at the actual
+    /// payload.
+    fn with_data_phase(i2c_addr: u8, data_len: usize) -> Result<Self> {
+        if data_len > MAX_DATA_PHASE_LEN {
+            return Err(
+                std::io::Error::new(ErrorKind::InvalidInput, "data_len > 65535 bytes").into(),
+            );
+        }
+
+        let i2c_write_addr = i2c_addr << 1;
+
+        Ok(Self {
+            scsi_vendor_command: USB2642_SCSI_OPCODE,
+            scsi_vendor_action_write_i2c: USB2642_I2C_WRITE_STREAM,
+            i2c_slave_address: i2c_write_addr,
+            i2c_unused: 0,
+            i2c_data_phase_length_high: ((data_len >> 8) & 0xff) as u8,
+            i2c_data_phase_length_low: (data_len & 0xff) as u8,
+            i2c_command_phase_length: 0,
+            i2c_command_phase_payload: Default::default(),
+        })
+    }
 }
 
 impl USB2642I2CCommand for USB2642I2CWriteCommand {}
 
+#[derive(Debug, Default)]
+#[repr(C)]
+struct USB2642I2CSetClockCommand {
+    scsi_vendor_command: u8,
+    scsi_vendor_action_set_clock: u8,
+    clock_divider_high: u8,
+    clock_divider_low: u8,
+}
+
+impl USB2642I2CSetClockCommand {
+    fn new(speed: I2cSpeed) -> Self {
+        let divider = speed.divider();
+        Self {
+            scsi_vendor_command: USB2642_SCSI_OPCODE,
+            scsi_vendor_action_set_clock: USB2642_I2C_SET_CLOCK,
+            clock_divider_high: ((divider >> 8) & 0xff) as u8,
+            clock_divider_low: (divider & 0xff) as u8,
+        }
+    }
+}
+
+impl USB2642I2CCommand for USB2642I2CSetClockCommand {}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct SgIoHdr<CMD: USB2642I2CCommand> {
@@ -193,22 +339,25 @@ pub struct SgIoHdr<CMD: USB2642I2CCommand> {
 }
 
 impl<CMD: USB2642I2CCommand> SgIoHdr<CMD> {
+    /// Build a `sg_io_hdr`. `command` and `sense` are borrowed rather than
+    /// owned so that the caller keeps them alive on its own stack frame for
+    /// as long as the resulting header is used in an `ioctl` call.
     pub fn new(
-        mut command: CMD,
+        command: &mut CMD,
         sg_dxfer: DataTransferDirection,
         data_buffer: *mut u8,
         data_len: usize,
+        sense: &mut [u8],
     ) -> Self {
-        let mut sense = [0u8; 64];
         Self {
-            interface_id: 'S' as i32,
+            interface_id: SG_INTERFACE_ID_ORIG as i32,
             dxfer_direction: sg_dxfer.to_i32().unwrap(),
             cmd_len: std::mem::size_of::<CMD>() as u8,
             mx_sb_len: sense.len() as u8,
             iovec_count: 0,
             dxfer_len: data_len as u32,
             dxferp: data_buffer,
-            cmdp: &mut command,
+            cmdp: command,
             sbp: sense.as_mut_ptr(),
             timeout: 3000,
             flags: 0,
@@ -242,45 +391,322 @@ impl USB2642I2C {
         })
     }
 
-    fn sg_ioctl<CMD: USB2642I2CCommand>(&self, sg_io_hdr: &SgIoHdr<CMD>) -> Result<()> {
-        if let Err(e) =
-            unsafe { convert_ioctl_res!(ioctl(self.sg_fd, SG_IO as ioctl_num_type, sg_io_hdr)) }
-        {
-            return Err(Error::new(ErrorKind::Other, e));
-        }
-        Ok(())
+    /// Opens `sg_dev` like [`USB2642I2C::open`] and additionally programs
+    /// the USB2642's I2C clock-divider register per `config`, so the bus
+    /// runs at `config.speed` instead of the firmware's default.
+    pub fn open_with_config<S: Into<String>>(sg_dev: S, config: Config) -> Result<Self> {
+        let mut usb2642 = Self::open(sg_dev)?;
+        usb2642.set_speed(config.speed)?;
+        Ok(usb2642)
     }
 
-    pub fn write(&mut self, i2c_addr: I2CAddress, data: &mut [u8]) -> Result<()> {
-        let command = USB2642I2CWriteCommand::new(i2c_addr, data)?;
-        let sgio = SgIoHdr::new(
-            command,
+    /// Programs the USB2642's I2C clock-divider configuration register,
+    /// changing the bus speed for every slave on the adapter.
+    ///
+    /// **Experimental:** see the note on [`I2cSpeed`] — the opcode and
+    /// divider values this builds on are reverse engineered and unverified
+    /// against a datasheet, so confirm the resulting bus speed with a
+    /// logic analyzer before relying on this in production.
+    pub fn set_speed(&mut self, speed: I2cSpeed) -> Result<()> {
+        let mut command = USB2642I2CSetClockCommand::new(speed);
+        let mut sense = [0u8; SENSE_BUFFER_LEN];
+        let mut sgio = SgIoHdr::new(
+            &mut command,
             DataTransferDirection::ToDev,
             std::ptr::null_mut(),
             0,
+            &mut sense,
         );
-        self.sg_ioctl(&sgio)
+        self.sg_ioctl(&mut sgio, &sense)
+    }
+
+    fn sg_ioctl<CMD: USB2642I2CCommand>(
+        &self,
+        sg_io_hdr: &mut SgIoHdr<CMD>,
+        sense: &[u8],
+    ) -> Result<()> {
+        unsafe { convert_ioctl_res!(ioctl(self.sg_fd, SG_IO as ioctl_num_type, &mut *sg_io_hdr)) }
+            .map_err(|e| std::io::Error::new(ErrorKind::Other, e))?;
+
+        let sense = &sense[..(sg_io_hdr.sb_len_wr as usize).min(sense.len())];
+        if let Some(err) = Error::from_sg_status(
+            sg_io_hdr.status,
+            sg_io_hdr.host_status,
+            sg_io_hdr.driver_status,
+            sense,
+        ) {
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Writes `data` to `i2c_addr`, chunking into multiple write-stream
+    /// commands if it exceeds the 65535 bytes a single SCSI data phase can carry.
+    pub fn write(&mut self, i2c_addr: I2CAddress, data: &mut [u8]) -> Result<()> {
+        let mut offset = 0;
+        for len in chunk_lengths(data.len(), MAX_DATA_PHASE_LEN) {
+            self.write_chunk(i2c_addr, &data[offset..offset + len])?;
+            offset += len;
+        }
+        Ok(())
+    }
+
+    fn write_chunk(&mut self, i2c_addr: I2CAddress, data: &[u8]) -> Result<()> {
+        let mut sense = [0u8; SENSE_BUFFER_LEN];
+
+        if data.len() <= MAX_INLINE_LEN {
+            let mut command = USB2642I2CWriteCommand::new(i2c_addr, data)?;
+            let mut sgio = SgIoHdr::new(
+                &mut command,
+                DataTransferDirection::ToDev,
+                std::ptr::null_mut(),
+                0,
+                &mut sense,
+            );
+            self.sg_ioctl(&mut sgio, &sense)
+        } else {
+            let mut buffer = data.to_vec();
+            let mut command = USB2642I2CWriteCommand::with_data_phase(i2c_addr, buffer.len())?;
+            let mut sgio = SgIoHdr::new(
+                &mut command,
+                DataTransferDirection::ToDev,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut sense,
+            );
+            self.sg_ioctl(&mut sgio, &sense)
+        }
     }
 
+    /// Writes `data` then reads `read_len` bytes back from `i2c_addr`,
+    /// chunking the read across multiple write-read-stream commands if it
+    /// exceeds the 65535 bytes a single SCSI data phase can carry.
+    ///
+    /// `data` is limited to [`MAX_INLINE_LEN`] bytes when combined with a
+    /// read in a single command, since the write-read-stream command has
+    /// only one SCSI data phase and it is used for the read; larger
+    /// payloads are sent with a preceding [`USB2642I2C::write`] call instead.
     pub fn write_read(
         &mut self,
         i2c_addr: I2CAddress,
         data: &[u8],
         read_len: usize,
     ) -> Result<Vec<u8>> {
-        let command = USB2642I2CWriteReadCommand::new(i2c_addr, data, read_len)?;
+        if data.len() > MAX_INLINE_LEN {
+            self.write(i2c_addr, &mut data.to_vec())?;
+            return self.read_chunked(i2c_addr, &[], read_len);
+        }
+        self.read_chunked(i2c_addr, data, read_len)
+    }
 
-        let mut out_buffer = [0u8; 9];
+    fn read_chunked(
+        &mut self,
+        i2c_addr: I2CAddress,
+        write_data: &[u8],
+        read_len: usize,
+    ) -> Result<Vec<u8>> {
+        let mut result = Vec::with_capacity(read_len);
+        let mut write_data = write_data;
 
-        let sgio = SgIoHdr::new(
-            command,
+        for len in chunk_lengths(read_len, MAX_DATA_PHASE_LEN) {
+            result.extend(self.read_chunk(i2c_addr, write_data, len)?);
+            write_data = &[];
+        }
+
+        Ok(result)
+    }
+
+    fn read_chunk(
+        &mut self,
+        i2c_addr: I2CAddress,
+        write_data: &[u8],
+        read_len: usize,
+    ) -> Result<Vec<u8>> {
+        let mut command = USB2642I2CWriteReadCommand::new(i2c_addr, write_data, read_len)?;
+
+        let mut out_buffer = vec![0u8; read_len];
+        let mut sense = [0u8; SENSE_BUFFER_LEN];
+
+        let mut sgio = SgIoHdr::new(
+            &mut command,
             DataTransferDirection::FromDev,
             out_buffer.as_mut_ptr(),
             read_len,
+            &mut sense,
         );
 
-        self.sg_ioctl(&sgio)?;
+        self.sg_ioctl(&mut sgio, &sense)?;
+
+        Ok(out_buffer)
+    }
 
-        Ok((&out_buffer[..read_len]).to_vec())
+    /// Executes a sequence of [`embedded_hal::i2c::Operation`]s against
+    /// `i2c_addr` as one logical transaction.
+    ///
+    /// Adjacent operations of the same kind (`Write`s or `Read`s) are
+    /// merged and issued as a single write-stream or write-read-stream call,
+    /// per `embedded_hal::i2c::I2c::transaction`'s contract that same-kind
+    /// operations go out back-to-back with no intervening stop/start; a run
+    /// of `Write`s immediately followed by a run of `Read`s is further
+    /// coalesced into one write-read-stream command using the USB2642's
+    /// repeated start between the two phases. This lets callers express
+    /// multi-step register protocols (write pointer, repeated start, read
+    /// block) in one call.
+    ///
+    /// The repeated start only applies when the merged writes fit in
+    /// [`MAX_INLINE_LEN`] bytes; longer writes go out as a separate
+    /// write-stream command ahead of the read (see
+    /// [`USB2642I2C::write_read`]), with no repeated start between them.
+    pub fn transaction(
+        &mut self,
+        i2c_addr: I2CAddress,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<()> {
+        use embedded_hal::i2c::Operation;
+
+        let mut i = 0;
+        while i < operations.len() {
+            let is_write = matches!(operations[i], Operation::Write(_));
+            let run_end = i + 1
+                + operations[i + 1..]
+                    .iter()
+                    .take_while(|op| matches!(op, Operation::Write(_)) == is_write)
+                    .count();
+
+            if is_write {
+                let mut write_data: Vec<u8> = operations[i..run_end]
+                    .iter()
+                    .flat_map(|op| match op {
+                        Operation::Write(data) => data.iter().copied(),
+                        _ => unreachable!(),
+                    })
+                    .collect();
+
+                let read_end = run_end
+                    + operations[run_end..]
+                        .iter()
+                        .take_while(|op| matches!(op, Operation::Read(_)))
+                        .count();
+
+                if read_end > run_end {
+                    let read_len: usize = operations[run_end..read_end]
+                        .iter()
+                        .map(|op| match op {
+                            Operation::Read(buffer) => buffer.len(),
+                            _ => unreachable!(),
+                        })
+                        .sum();
+
+                    let data = self.write_read(i2c_addr, &write_data, read_len)?;
+                    let mut offset = 0;
+                    for op in &mut operations[run_end..read_end] {
+                        if let Operation::Read(buffer) = op {
+                            buffer.copy_from_slice(&data[offset..offset + buffer.len()]);
+                            offset += buffer.len();
+                        }
+                    }
+
+                    i = read_end;
+                    continue;
+                }
+
+                self.write(i2c_addr, &mut write_data)?;
+            } else {
+                let read_len: usize = operations[i..run_end]
+                    .iter()
+                    .map(|op| match op {
+                        Operation::Read(buffer) => buffer.len(),
+                        _ => unreachable!(),
+                    })
+                    .sum();
+
+                let data = self.read_chunked(i2c_addr, &[], read_len)?;
+                let mut offset = 0;
+                for op in &mut operations[i..run_end] {
+                    if let Operation::Read(buffer) = op {
+                        buffer.copy_from_slice(&data[offset..offset + buffer.len()]);
+                        offset += buffer.len();
+                    }
+                }
+            }
+
+            i = run_end;
+        }
+
+        Ok(())
+    }
+
+    /// Probes whether a slave acknowledges `addr`, the equivalent of a
+    /// single cell of `i2cdetect`'s output.
+    ///
+    /// Attempts a zero-length write and classifies the result: `Ok(true)`
+    /// if it was acknowledged, `Ok(false)` if the slave did not respond
+    /// ([`Error::NoAcknowledge`]), and `Err` for any other failure (bus
+    /// error, I/O error).
+    pub fn probe(&mut self, addr: I2CAddress) -> Result<bool> {
+        match self.write(addr, &mut []) {
+            Ok(()) => Ok(true),
+            Err(Error::NoAcknowledge { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Scans every usable 7-bit I2C address and returns the ones that
+    /// acknowledge, similar to `i2cdetect`. Addresses reserved for the
+    /// general call and ten-bit addressing (`0x00..=0x07`, `0x78..=0x7f`)
+    /// are skipped; note this is a wider reserved range than `i2cdetect`'s
+    /// own default of `0x00..=0x02`/`0x78..=0x7f`, so this will skip a few
+    /// addresses (`0x03..=0x07`) that `i2cdetect` probes by default.
+    pub fn scan(&mut self) -> Result<Vec<I2CAddress>> {
+        let mut responders = Vec::new();
+        for addr in 0x08..=0x77 {
+            if self.probe(addr)? {
+                responders.push(addr);
+            }
+        }
+        Ok(responders)
+    }
+}
+
+impl embedded_hal::i2c::ErrorType for USB2642I2C {
+    type Error = Error;
+}
+
+impl embedded_hal::i2c::I2c<embedded_hal::i2c::SevenBitAddress> for USB2642I2C {
+    fn transaction(
+        &mut self,
+        address: embedded_hal::i2c::SevenBitAddress,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<()> {
+        USB2642I2C::transaction(self, address, operations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_lengths_zero_length_yields_one_zero_chunk() {
+        assert_eq!(chunk_lengths(0, MAX_DATA_PHASE_LEN), vec![0]);
+    }
+
+    #[test]
+    fn chunk_lengths_fits_in_one_chunk() {
+        assert_eq!(chunk_lengths(10, MAX_DATA_PHASE_LEN), vec![10]);
+    }
+
+    #[test]
+    fn chunk_lengths_splits_on_exact_boundary() {
+        assert_eq!(chunk_lengths(2 * MAX_DATA_PHASE_LEN, MAX_DATA_PHASE_LEN), vec![MAX_DATA_PHASE_LEN, MAX_DATA_PHASE_LEN]);
+    }
+
+    #[test]
+    fn chunk_lengths_splits_with_remainder() {
+        assert_eq!(
+            chunk_lengths(MAX_DATA_PHASE_LEN + 1, MAX_DATA_PHASE_LEN),
+            vec![MAX_DATA_PHASE_LEN, 1]
+        );
     }
 }
@@ -0,0 +1,280 @@
+//! Driver for the PCA953x/TCA64xx family of I2C GPIO expanders (PCA9536,
+//! PCA9534/9554, PCA9535/9555, TCA6408, TCA6416, ...), modeled on the
+//! register layout Linux's `gpio-pca953x` driver uses: an Input, Output,
+//! Polarity Inversion and Configuration register per port, doubled up into
+//! low/high byte pairs for 16-bit parts.
+
+use std::{cell::RefCell, io::ErrorKind, rc::Rc};
+
+use crate::{I2CAddress, Result, USB2642I2C};
+
+/// Port width of a PCA953x/TCA64xx part, which determines how many GPIO
+/// pins it exposes and whether each register is one or two bytes wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortWidth {
+    /// 4 pins, 1 byte per register (e.g. PCA9536).
+    Bits4,
+    /// 8 pins, 1 byte per register (e.g. PCA9534, PCA9554, TCA6408).
+    Bits8,
+    /// 16 pins, 2 bytes (low port, then high port) per register (e.g.
+    /// PCA9535, PCA9555, TCA6416).
+    Bits16,
+}
+
+impl PortWidth {
+    fn pin_count(self) -> u8 {
+        match self {
+            PortWidth::Bits4 => 4,
+            PortWidth::Bits8 => 8,
+            PortWidth::Bits16 => 16,
+        }
+    }
+
+    fn registers_per_function(self) -> u8 {
+        match self {
+            PortWidth::Bits4 | PortWidth::Bits8 => 1,
+            PortWidth::Bits16 => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Register {
+    Input,
+    Output,
+    Polarity,
+    Configuration,
+}
+
+/// Auto-Increment bit (bit 7 of the command byte). 16-bit parts need this
+/// set to walk from a function's low byte to its high byte within a single
+/// transaction instead of re-latching the low byte on every access.
+const AUTO_INCREMENT: u8 = 0x80;
+
+impl Register {
+    /// Register address of this function's low byte. 16-bit parts carry
+    /// the high byte at `address + 1`, mirroring how `gpio-pca953x`
+    /// multiplies the register number by the part's bank count.
+    fn address(self, width: PortWidth) -> u8 {
+        let function = match self {
+            Register::Input => 0,
+            Register::Output => 1,
+            Register::Polarity => 2,
+            Register::Configuration => 3,
+        };
+        function * width.registers_per_function()
+    }
+
+    /// Command byte to send for accessing this function: the address from
+    /// [`Register::address`], with the [`AUTO_INCREMENT`] bit set on 16-bit
+    /// parts so the low/high byte pair is read or written as one sequential
+    /// access.
+    fn command(self, width: PortWidth) -> u8 {
+        let address = self.address(width);
+        if width.registers_per_function() == 2 {
+            address | AUTO_INCREMENT
+        } else {
+            address
+        }
+    }
+}
+
+/// Pin direction, matching the sense of the Configuration register (`1` =
+/// input, `0` = output).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Output,
+    Input,
+}
+
+/// A PCA953x/TCA64xx GPIO expander on a [`USB2642I2C`] bus.
+pub struct Pca953x {
+    usb2642: USB2642I2C,
+    address: I2CAddress,
+    width: PortWidth,
+}
+
+impl Pca953x {
+    pub fn new(usb2642: USB2642I2C, address: I2CAddress, width: PortWidth) -> Self {
+        Self {
+            usb2642,
+            address,
+            width,
+        }
+    }
+
+    /// Splits this expander into one [`Pin`] handle per GPIO, sharing the
+    /// underlying bus connection.
+    pub fn split(self) -> Vec<Pin> {
+        let pin_count = self.width.pin_count();
+        let expander = Rc::new(RefCell::new(self));
+        (0..pin_count)
+            .map(|pin| Pin {
+                expander: expander.clone(),
+                pin,
+            })
+            .collect()
+    }
+
+    pub fn set_pin_direction(&mut self, pin: u8, direction: Direction) -> Result<()> {
+        self.check_pin(pin)?;
+        let mut mask = self.read_register(Register::Configuration)?;
+        match direction {
+            Direction::Output => mask &= !(1 << pin),
+            Direction::Input => mask |= 1 << pin,
+        }
+        self.write_register(Register::Configuration, mask)
+    }
+
+    pub fn set_pin_polarity_inverted(&mut self, pin: u8, inverted: bool) -> Result<()> {
+        self.check_pin(pin)?;
+        let mut mask = self.read_register(Register::Polarity)?;
+        if inverted {
+            mask |= 1 << pin;
+        } else {
+            mask &= !(1 << pin);
+        }
+        self.write_register(Register::Polarity, mask)
+    }
+
+    pub fn set_high(&mut self, pin: u8) -> Result<()> {
+        self.check_pin(pin)?;
+        let mask = self.read_register(Register::Output)? | (1 << pin);
+        self.write_register(Register::Output, mask)
+    }
+
+    pub fn set_low(&mut self, pin: u8) -> Result<()> {
+        self.check_pin(pin)?;
+        let mask = self.read_register(Register::Output)? & !(1 << pin);
+        self.write_register(Register::Output, mask)
+    }
+
+    pub fn is_high(&mut self, pin: u8) -> Result<bool> {
+        self.check_pin(pin)?;
+        let mask = self.read_register(Register::Input)?;
+        Ok(mask & (1 << pin) != 0)
+    }
+
+    fn check_pin(&self, pin: u8) -> Result<()> {
+        if pin >= self.width.pin_count() {
+            return Err(std::io::Error::new(ErrorKind::InvalidInput, "pin out of range").into());
+        }
+        Ok(())
+    }
+
+    fn read_register(&mut self, register: Register) -> Result<u16> {
+        let command = register.command(self.width);
+        if self.width.registers_per_function() == 1 {
+            let data = self.usb2642.write_read(self.address, &[command], 1)?;
+            Ok(data[0] as u16)
+        } else {
+            let data = self.usb2642.write_read(self.address, &[command], 2)?;
+            Ok(u16::from_le_bytes([data[0], data[1]]))
+        }
+    }
+
+    fn write_register(&mut self, register: Register, value: u16) -> Result<()> {
+        let command = register.command(self.width);
+        if self.width.registers_per_function() == 1 {
+            let mut data = [command, value as u8];
+            self.usb2642.write(self.address, &mut data)
+        } else {
+            let bytes = value.to_le_bytes();
+            let mut data = [command, bytes[0], bytes[1]];
+            self.usb2642.write(self.address, &mut data)
+        }
+    }
+}
+
+/// A single GPIO pin of a [`Pca953x`] expander, sharing the expander's bus
+/// connection with every other [`Pin`] produced by [`Pca953x::split`].
+pub struct Pin {
+    expander: Rc<RefCell<Pca953x>>,
+    pin: u8,
+}
+
+impl Pin {
+    pub fn set_high(&mut self) -> Result<()> {
+        self.expander.borrow_mut().set_high(self.pin)
+    }
+
+    pub fn set_low(&mut self) -> Result<()> {
+        self.expander.borrow_mut().set_low(self.pin)
+    }
+
+    pub fn is_high(&mut self) -> Result<bool> {
+        self.expander.borrow_mut().is_high(self.pin)
+    }
+}
+
+impl embedded_hal::digital::ErrorType for Pin {
+    type Error = crate::Error;
+}
+
+impl embedded_hal::digital::OutputPin for Pin {
+    fn set_high(&mut self) -> Result<()> {
+        Pin::set_high(self)
+    }
+
+    fn set_low(&mut self) -> Result<()> {
+        Pin::set_low(self)
+    }
+}
+
+impl embedded_hal::digital::InputPin for Pin {
+    fn is_high(&mut self) -> Result<bool> {
+        Pin::is_high(self)
+    }
+
+    fn is_low(&mut self) -> Result<bool> {
+        Ok(!Pin::is_high(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_is_bare_on_8bit_parts() {
+        assert_eq!(Register::Input.address(PortWidth::Bits8), 0);
+        assert_eq!(Register::Output.address(PortWidth::Bits8), 1);
+        assert_eq!(Register::Polarity.address(PortWidth::Bits8), 2);
+        assert_eq!(Register::Configuration.address(PortWidth::Bits8), 3);
+    }
+
+    #[test]
+    fn address_is_doubled_on_16bit_parts() {
+        assert_eq!(Register::Input.address(PortWidth::Bits16), 0);
+        assert_eq!(Register::Output.address(PortWidth::Bits16), 2);
+        assert_eq!(Register::Polarity.address(PortWidth::Bits16), 4);
+        assert_eq!(Register::Configuration.address(PortWidth::Bits16), 6);
+    }
+
+    #[test]
+    fn command_has_no_auto_increment_on_8bit_parts() {
+        for register in [
+            Register::Input,
+            Register::Output,
+            Register::Polarity,
+            Register::Configuration,
+        ] {
+            assert_eq!(register.command(PortWidth::Bits8), register.address(PortWidth::Bits8));
+        }
+    }
+
+    #[test]
+    fn command_sets_auto_increment_on_16bit_parts() {
+        for register in [
+            Register::Input,
+            Register::Output,
+            Register::Polarity,
+            Register::Configuration,
+        ] {
+            assert_eq!(
+                register.command(PortWidth::Bits16),
+                register.address(PortWidth::Bits16) | AUTO_INCREMENT
+            );
+        }
+    }
+}
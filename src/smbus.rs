@@ -0,0 +1,201 @@
+//! SMBus transactions (Quick Command, Byte/Word/Block Read and Write) layered
+//! on top of [`USB2642I2C::write`]/[`USB2642I2C::write_read`], with optional
+//! Packet Error Checking (PEC).
+
+use std::io::ErrorKind;
+
+use crate::{Error, I2CAddress, Result, USB2642I2C};
+
+/// Largest payload a single SMBus block transfer may carry.
+pub const SMBUS_BLOCK_MAX_LEN: usize = 32;
+
+/// CRC-8 with polynomial 0x07 (x^8+x^2+x+1), initial value 0, processed
+/// MSB-first — the SMBus Packet Error Check algorithm.
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// SMBus transactions layered on top of a [`USB2642I2C`] bus.
+///
+/// Mirrors the transactions Linux's `i2c-smbus` exposes: Quick Command,
+/// Read/Write Byte, Read/Write Byte Data, Read/Write Word Data and Block
+/// Read/Write. When constructed with [`Smbus::with_pec`], every transaction
+/// except Quick Command appends (for writes) or validates and strips (for
+/// reads) a CRC-8 Packet Error Check byte.
+pub struct Smbus<'a> {
+    usb2642: &'a mut USB2642I2C,
+    pec: bool,
+}
+
+impl<'a> Smbus<'a> {
+    /// Wraps `usb2642` without Packet Error Checking.
+    pub fn new(usb2642: &'a mut USB2642I2C) -> Self {
+        Self { usb2642, pec: false }
+    }
+
+    /// Wraps `usb2642` with Packet Error Checking enabled.
+    pub fn with_pec(usb2642: &'a mut USB2642I2C) -> Self {
+        Self { usb2642, pec: true }
+    }
+
+    /// SMBus Quick Command: addresses the slave with `read` selecting the
+    /// R/W bit, with no data phase at all.
+    pub fn quick(&mut self, addr: I2CAddress, read: bool) -> Result<()> {
+        if read {
+            self.usb2642.write_read(addr, &[], 0).map(|_| ())
+        } else {
+            self.usb2642.write(addr, &mut [])
+        }
+    }
+
+    /// SMBus Receive Byte: reads a single byte with no command code.
+    pub fn read_byte(&mut self, addr: I2CAddress) -> Result<u8> {
+        Ok(self.do_read(addr, &[], 1)?[0])
+    }
+
+    /// SMBus Send Byte: writes a single byte with no command code.
+    pub fn write_byte(&mut self, addr: I2CAddress, value: u8) -> Result<()> {
+        self.do_write(addr, &[value])
+    }
+
+    /// SMBus Read Byte Data: reads one data byte addressed by `command`.
+    pub fn read_byte_data(&mut self, addr: I2CAddress, command: u8) -> Result<u8> {
+        Ok(self.do_read(addr, &[command], 1)?[0])
+    }
+
+    /// SMBus Write Byte Data: writes one data byte addressed by `command`.
+    pub fn write_byte_data(&mut self, addr: I2CAddress, command: u8, value: u8) -> Result<()> {
+        self.do_write(addr, &[command, value])
+    }
+
+    /// SMBus Read Word Data: reads two data bytes (little-endian) addressed
+    /// by `command`.
+    pub fn read_word_data(&mut self, addr: I2CAddress, command: u8) -> Result<u16> {
+        let data = self.do_read(addr, &[command], 2)?;
+        Ok(u16::from_le_bytes([data[0], data[1]]))
+    }
+
+    /// SMBus Write Word Data: writes two data bytes (little-endian)
+    /// addressed by `command`.
+    pub fn write_word_data(&mut self, addr: I2CAddress, command: u8, value: u16) -> Result<()> {
+        let value = value.to_le_bytes();
+        self.do_write(addr, &[command, value[0], value[1]])
+    }
+
+    /// SMBus Block Read: reads the length-prefixed block addressed by
+    /// `command` (up to [`SMBUS_BLOCK_MAX_LEN`] bytes), returning just the
+    /// data bytes.
+    ///
+    /// The USB2642 has no native SMBus block awareness, so unlike the other
+    /// transactions this issues two I2C transactions instead of one: the
+    /// length byte has to be read before the driver knows how much more to
+    /// read.
+    pub fn read_block_data(&mut self, addr: I2CAddress, command: u8) -> Result<Vec<u8>> {
+        let len_byte = self.usb2642.write_read(addr, &[command], 1)?[0];
+        let len = (len_byte as usize).min(SMBUS_BLOCK_MAX_LEN);
+
+        let read_len = if self.pec { len + 1 } else { len };
+        let mut data = self.usb2642.write_read(addr, &[], read_len)?;
+
+        if self.pec {
+            let received_pec = data.pop().ok_or(Error::Pec)?;
+            let mut input = vec![addr << 1, command, (addr << 1) | 1, len_byte];
+            input.extend_from_slice(&data);
+            if crc8(&input) != received_pec {
+                return Err(Error::Pec);
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// SMBus Block Write: writes the length-prefixed block addressed by
+    /// `command`. `data` must be at most [`SMBUS_BLOCK_MAX_LEN`] bytes.
+    pub fn write_block_data(&mut self, addr: I2CAddress, command: u8, data: &[u8]) -> Result<()> {
+        if data.len() > SMBUS_BLOCK_MAX_LEN {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "block data > 32 bytes",
+            )
+            .into());
+        }
+
+        let mut payload = Vec::with_capacity(2 + data.len());
+        payload.push(command);
+        payload.push(data.len() as u8);
+        payload.extend_from_slice(data);
+
+        self.do_write(addr, &payload)
+    }
+
+    /// Writes `payload` (everything after the slave address byte), appending
+    /// a PEC byte computed over the address and `payload` when enabled.
+    fn do_write(&mut self, addr: I2CAddress, payload: &[u8]) -> Result<()> {
+        let mut data = payload.to_vec();
+        if self.pec {
+            let mut input = Vec::with_capacity(1 + payload.len());
+            input.push(addr << 1);
+            input.extend_from_slice(payload);
+            data.push(crc8(&input));
+        }
+        self.usb2642.write(addr, &mut data)
+    }
+
+    /// Writes `write_payload` then reads `read_len` bytes, validating and
+    /// stripping the trailing PEC byte when enabled.
+    fn do_read(&mut self, addr: I2CAddress, write_payload: &[u8], read_len: usize) -> Result<Vec<u8>> {
+        let total_len = if self.pec { read_len + 1 } else { read_len };
+        let mut data = self.usb2642.write_read(addr, write_payload, total_len)?;
+
+        if self.pec {
+            let received_pec = data.pop().ok_or(Error::Pec)?;
+            // A write phase only goes out on the wire when `write_payload`
+            // is non-empty (e.g. Receive Byte has none), so the write
+            // address byte only enters the PEC input in that case.
+            let mut input = Vec::with_capacity(2 + write_payload.len() + data.len());
+            if !write_payload.is_empty() {
+                input.push(addr << 1);
+                input.extend_from_slice(write_payload);
+            }
+            input.push((addr << 1) | 1);
+            input.extend_from_slice(&data);
+            if crc8(&input) != received_pec {
+                return Err(Error::Pec);
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc8_of_empty_input_is_zero() {
+        assert_eq!(crc8(&[]), 0);
+    }
+
+    #[test]
+    fn crc8_is_deterministic() {
+        assert_eq!(crc8(&[0x20, 0x00, 0x80]), crc8(&[0x20, 0x00, 0x80]));
+    }
+
+    #[test]
+    fn crc8_is_sensitive_to_byte_order() {
+        assert_ne!(crc8(&[0x20, 0x00, 0x80]), crc8(&[0x80, 0x00, 0x20]));
+    }
+
+    #[test]
+    fn crc8_is_sensitive_to_single_bit_flips() {
+        assert_ne!(crc8(&[0x20, 0x00, 0x80]), crc8(&[0x21, 0x00, 0x80]));
+    }
+}